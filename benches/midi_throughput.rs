@@ -0,0 +1,74 @@
+use std::time::{Duration, Instant};
+
+use bevy::prelude::*;
+use bevy_midi::{test_utils::mock_midi::MockMidiSender, MidiInputPlugin, MidiInputReader};
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+
+// Keys sent per frame
+const EVENT_COUNTS: [u64; 3] = [100, 1_000, 10_000];
+// Slowest we allow the pipeline to get at 1 000 keys per frame
+const MAX_TIME_PER_EVENT: Duration = Duration::from_micros(1);
+// Frames to average over when checking `MAX_TIME_PER_EVENT`
+const CHECK_FRAMES: u32 = 100;
+
+// An app with just the input pipeline and a mock device feeding it
+fn setup() -> (App, MockMidiSender) {
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins).add_plugin(MidiInputPlugin);
+    // Run startup and the first frame before measuring
+    app.update();
+
+    let mock = MockMidiSender::new(app.world.resource::<MidiInputReader>().sender());
+    (app, mock)
+}
+
+// Sends `count` keys through the channel and syncs them in one frame.
+// Every press is released right away so each one goes through the full press/release path,
+// instead of being treated as a repeat of a key that's still held.
+fn run_frame(app: &mut App, mock: &MockMidiSender, count: u64) {
+    for index in 0..count / 2 {
+        let note = (index % 128) as u8;
+        mock.send_note_on(note, 100);
+        mock.send_note_off(note);
+    }
+    app.update();
+}
+
+fn midi_throughput(c: &mut Criterion) {
+    let mut group = c.benchmark_group("MidiInputThroughput");
+    for count in EVENT_COUNTS {
+        group.throughput(Throughput::Elements(count));
+        group.bench_with_input(BenchmarkId::from_parameter(count), &count, |b, &count| {
+            let (mut app, mock) = setup();
+            b.iter(|| run_frame(&mut app, &mock, count));
+        });
+    }
+    group.finish();
+
+    // Criterion only reports, so check the budget ourselves to catch regressions.
+    // Only under `cargo bench`: `cargo test` runs benches once in an unoptimized build.
+    if std::env::args().any(|arg| arg == "--bench") {
+        check_time_per_event();
+    }
+}
+
+// Fails if 1 000 keys per frame take longer than `MAX_TIME_PER_EVENT` each
+fn check_time_per_event() {
+    let (mut app, mock) = setup();
+    let count = 1_000;
+    let start = Instant::now();
+    for _ in 0..CHECK_FRAMES {
+        run_frame(&mut app, &mock, count);
+    }
+    let time_per_event = start.elapsed() / (CHECK_FRAMES * count as u32);
+    assert!(
+        time_per_event < MAX_TIME_PER_EVENT,
+        "Processing took {:?} per event at {} events per frame (limit {:?})",
+        time_per_event,
+        count,
+        MAX_TIME_PER_EVENT
+    );
+}
+
+criterion_group!(benches, midi_throughput);
+criterion_main!(benches);
@@ -0,0 +1,30 @@
+use bevy::prelude::*;
+use bevy_framepace::{FramepacePlugin, FramepaceSettings, Limiter};
+
+use crate::settings::Settings;
+
+// Caps the framerate so every frame takes a similar amount of time.
+// MIDI keys are synced once per frame, so uneven frame times make the
+// delay between pressing a key and seeing it jitter from frame to frame.
+// A steady framerate keeps `time.delta()` predictable for anything timed off of it.
+pub struct FramerateLimiterPlugin;
+
+impl Plugin for FramerateLimiterPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugin(FramepacePlugin)
+            .add_system(sync_framerate_limit);
+    }
+}
+
+// Applies the `max_fps` setting to the limiter whenever settings change
+fn sync_framerate_limit(settings: Res<Settings>, mut framepace: ResMut<FramepaceSettings>) {
+    if !settings.is_changed() {
+        return;
+    }
+
+    framepace.limiter = match settings.max_fps {
+        // 0 means match the monitor's refresh rate
+        0 => Limiter::Auto,
+        max_fps => Limiter::from_framerate(max_fps as f64),
+    };
+}
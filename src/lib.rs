@@ -0,0 +1,1039 @@
+use std::{collections::HashMap, error::Error, fmt, time::Duration};
+
+use bevy::{
+    ecs::system::SystemState,
+    prelude::*,
+    window::WindowFocused,
+    winit::{UpdateMode, WinitSettings},
+};
+use bevy_egui::{egui, EguiContexts, EguiPlugin};
+
+use crossbeam_channel::{Receiver, Sender};
+use framerate_limiter::FramerateLimiterPlugin;
+use midir::{Ignore, MidiInput, MidiInputConnection, MidiInputPort};
+use network::UdpBroadcastPlugin;
+use osc_input::OscInputPlugin;
+use pitch_ring::PitchRingPlugin;
+use settings::{Settings, SettingsPlugin};
+
+mod framerate_limiter;
+mod network;
+mod osc_input;
+mod pitch_ring;
+mod settings;
+// Stand-ins for MIDI devices, for tests and benchmarks
+#[cfg(any(test, feature = "mock"))]
+pub mod test_utils;
+
+// State to manage
+#[derive(Resource)]
+pub struct MidiSetupState {
+    // An instance to access MIDI devices and input
+    input: MidiInput,
+    // Available ports
+    available_ports: Vec<MidiInputPort>,
+}
+
+pub struct MidiResponse(MidiMessage, MidiSource);
+
+// Where a key came from, so device-specific handling doesn't leak into other inputs
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum MidiSource {
+    Device,
+    Osc,
+}
+
+#[derive(Resource)]
+pub struct MidiInputReader {
+    receiver: Receiver<MidiResponse>,
+    sender: Sender<MidiResponse>,
+}
+
+impl MidiInputReader {
+    // A sender for pushing messages into the channel, like a device connection would
+    pub fn sender(&self) -> Sender<MidiResponse> {
+        self.sender.clone()
+    }
+}
+
+#[derive(Resource)]
+pub struct MidiInputState {
+    latest_key: Option<MidiInputKey>,
+    // Is a MIDI device currently connected?
+    connected: bool,
+    // Keys currently held down
+    held_keys: HashMap<u8, HeldKey>,
+    // How many stuck keys were released automatically
+    stuck_keys_released: u32,
+    // Octaves to shift incoming keys by, for devices that don't start at the expected note
+    octave: i8,
+}
+
+// A key that's currently held down
+#[derive(Clone, Copy)]
+struct HeldKey {
+    // Time (in seconds) it was pressed or last touched
+    pressed_at: f32,
+    // Velocity it was pressed with, so it can be re-sent when the octave changes
+    intensity: u8,
+    // Where the press came from, so a device disconnecting only releases its own keys
+    source: MidiSource,
+}
+
+// How far the octave offset can shift keys in either direction
+const MAX_OCTAVE_OFFSET: i8 = 4;
+
+// Note names within an octave, starting from C
+pub const NOTE_NAMES: [&str; 12] = [
+    "C", "C#", "D", "D#", "E", "F", "F#", "G", "G#", "A", "A#", "B",
+];
+
+// Note id of middle C (C4)
+const MIDDLE_C: u8 = 60;
+
+impl MidiInputState {
+    // Shifts a device note id by the octave offset, None if it lands outside the MIDI range
+    fn apply_octave(&self, id: u8) -> Option<u8> {
+        u8::try_from(id as i16 + self.octave as i16 * 12)
+            .ok()
+            .filter(|id| *id <= 127)
+    }
+
+    // Releases every held key, returning the release events to send
+    fn release_all(&mut self) -> Vec<MidiInputKey> {
+        self.held_keys
+            .drain()
+            .map(|(id, _)| MidiInputKey {
+                event: MidiEvents::Released,
+                id,
+                intensity: 0,
+            })
+            .collect()
+    }
+
+    // Releases the keys held from one source, returning the release events to send
+    fn release_from(&mut self, source: MidiSource) -> Vec<MidiInputKey> {
+        let ids: Vec<u8> = self
+            .held_keys
+            .iter()
+            .filter(|(_, held_key)| held_key.source == source)
+            .map(|(id, _)| *id)
+            .collect();
+
+        ids.into_iter()
+            .map(|id| {
+                self.held_keys.remove(&id);
+                MidiInputKey {
+                    event: MidiEvents::Released,
+                    id,
+                    intensity: 0,
+                }
+            })
+            .collect()
+    }
+
+    // Changes the octave offset, moving held keys along so their releases still match.
+    // Returns the events to send: a release for every old id, then a press for every new one.
+    // Keys shifted out of the MIDI range are only released.
+    fn set_octave(&mut self, octave: i8) -> Vec<MidiInputKey> {
+        let delta = (octave - self.octave) as i16 * 12;
+        self.octave = octave;
+
+        let mut releases = Vec::new();
+        let mut presses = Vec::new();
+        let mut held_keys = HashMap::new();
+        for (id, held_key) in self.held_keys.drain() {
+            releases.push(MidiInputKey {
+                event: MidiEvents::Released,
+                id,
+                intensity: 0,
+            });
+
+            let Some(new_id) = u8::try_from(id as i16 + delta).ok().filter(|id| *id <= 127) else {
+                continue;
+            };
+            presses.push(MidiInputKey {
+                event: MidiEvents::Pressed,
+                id: new_id,
+                intensity: held_key.intensity,
+            });
+            held_keys.insert(new_id, held_key);
+        }
+
+        self.held_keys = held_keys;
+        releases.extend(presses);
+        releases
+    }
+}
+
+// Name of a MIDI note, e.g. 60 => "C4"
+fn note_name(id: u8) -> String {
+    format!("{}{}", NOTE_NAMES[id as usize % 12], id as i16 / 12 - 1)
+}
+
+// The currently connected MIDI device
+#[derive(Resource, Default)]
+pub struct ConnectedDeviceInfo {
+    name: String,
+}
+
+// Translates incoming note ids for controllers that don't use linear note numbering
+// (e.g. MPC-style drum pads). Loaded per device, empty means no remapping.
+#[derive(Resource, Default)]
+pub struct NoteRemap(HashMap<u8, u8>);
+
+// Marker for the HUD label showing the connected device
+#[derive(Component)]
+struct ConnectedDeviceLabel;
+
+// Distance of the device label from the window corner (in pixels)
+const DEVICE_LABEL_MARGIN: f32 = 16.0;
+
+// Font for the device label (in `assets`), Bevy doesn't ship a default one
+const DEVICE_LABEL_FONT: &str = "fonts/Hack-Regular.ttf";
+
+#[derive(Default, Debug, Clone, Copy)]
+pub enum MidiEvents {
+    #[default]
+    Pressed,
+    Released,
+    Holding,
+}
+
+// Event for MIDI key input
+#[derive(Default, Clone, Copy)]
+pub struct MidiInputKey {
+    event: MidiEvents,
+    id: u8,
+    intensity: u8,
+}
+
+impl MidiEvents {
+    // The MIDI status byte this event is parsed from
+    pub fn status(&self) -> u8 {
+        match self {
+            MidiEvents::Pressed => 144,
+            MidiEvents::Released => 128,
+            MidiEvents::Holding => 160,
+        }
+    }
+}
+
+impl MidiInputKey {
+    // Parses a raw MIDI message into a key input, None for anything that isn't a key
+    // message = array of keyboard data. [keyEvent, keyId, strength]
+    pub fn from_message(message: &[u8]) -> Option<MidiInputKey> {
+        if message.len() < 3 {
+            return None;
+        }
+
+        // The low nibble is the channel, we treat every channel the same
+        let event_type = match (message[0] & 0xF0, message[2]) {
+            // Plenty of devices send "note on" with no velocity instead of "note off"
+            (144, 0) => MidiEvents::Released,
+            (144, _) => MidiEvents::Pressed,
+            (128, _) => MidiEvents::Released,
+            (160, _) => MidiEvents::Holding,
+            _ => return None,
+        };
+
+        Some(MidiInputKey {
+            event: event_type,
+            id: message[1],
+            intensity: message[2],
+        })
+    }
+}
+
+// A raw MIDI message we know how to handle
+#[derive(Clone, Copy)]
+pub enum MidiMessage {
+    Key(MidiInputKey),
+    // Aftertouch for the whole channel rather than one key,
+    // `sync_keys` turns it into `Holding` for every held key
+    ChannelPressure(u8),
+}
+
+impl MidiMessage {
+    // Parses a raw MIDI message, None for anything that isn't a key or channel pressure
+    pub fn from_bytes(message: &[u8]) -> Option<MidiMessage> {
+        // Channel pressure is only [status, pressure], on any channel
+        if message.first()? & 0xF0 == 208 {
+            return message
+                .get(1)
+                .map(|pressure| MidiMessage::ChannelPressure(*pressure));
+        }
+
+        MidiInputKey::from_message(message).map(MidiMessage::Key)
+    }
+}
+
+// Event to trigger a notification
+#[derive(Default)]
+struct SelectDeviceEvent(usize);
+
+// Event to drop the current device connection
+struct DisconnectDeviceEvent;
+
+// Is the device selection window showing?
+#[derive(Resource)]
+struct DevicesWindow {
+    open: bool,
+}
+
+// Key to show or hide the device selection window
+const DEVICES_WINDOW_KEY: KeyCode = KeyCode::F1;
+
+// Everything that can go wrong talking to MIDI devices
+#[derive(Debug, Clone)]
+pub enum MidiError {
+    InitFailed(String),
+    ConnectFailed(String),
+    PortNotFound,
+    InvalidPortIndex(usize),
+}
+
+impl fmt::Display for MidiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MidiError::InitFailed(error) => write!(f, "Couldn't initialize MIDI input: {}", error),
+            MidiError::ConnectFailed(error) => write!(
+                f,
+                "Couldn't connect to that port. Did the devices change recently? ({})",
+                error
+            ),
+            MidiError::PortNotFound => write!(f, "MIDI port not found"),
+            MidiError::InvalidPortIndex(index) => {
+                write!(f, "Invalid input port selected: {}", index)
+            }
+        }
+    }
+}
+
+impl Error for MidiError {}
+
+// Event to surface MIDI errors to the user
+pub struct MidiErrorEvent(MidiError);
+
+// Errors currently shown as toasts, with how long they have left on screen
+#[derive(Resource, Default)]
+struct MidiErrorToasts(Vec<(MidiError, Timer)>);
+
+// How long an error toast stays on screen (in seconds)
+const ERROR_TOAST_DURATION: f32 = 5.0;
+
+// Owns the message channel between the MIDI connection and Bevy
+// and syncs incoming keys into `MidiInputState`
+pub struct MidiInputPlugin;
+
+impl Plugin for MidiInputPlugin {
+    fn build(&self, app: &mut App) {
+        // We create a message channel to communicate between MIDI protocol and Bevy state
+        let (sender, receiver) = crossbeam_channel::unbounded::<MidiResponse>();
+        app.insert_resource(MidiInputReader { sender, receiver })
+            .insert_resource(MidiInputState {
+                latest_key: None,
+                connected: false,
+                held_keys: HashMap::new(),
+                stuck_keys_released: 0,
+                octave: 0,
+            })
+            .init_resource::<NoteRemap>()
+            .add_event::<MidiInputKey>()
+            .add_system(sync_keys);
+    }
+}
+
+// Everything the app needs on top of Bevy's default plugins:
+// the MIDI device connection, settings, UI and the optional network inputs and outputs
+pub struct MidiPlaygroundPlugin;
+
+impl Plugin for MidiPlaygroundPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugin(EguiPlugin)
+            .add_plugin(SettingsPlugin)
+            .add_plugin(FramerateLimiterPlugin)
+            .add_plugin(MidiInputPlugin)
+            .add_plugin(UdpBroadcastPlugin)
+            .add_plugin(OscInputPlugin)
+            .add_plugin(PitchRingPlugin)
+            .add_event::<SelectDeviceEvent>()
+            .add_event::<DisconnectDeviceEvent>()
+            .insert_resource(DevicesWindow { open: true })
+            .add_event::<MidiErrorEvent>()
+            .init_resource::<MidiErrorToasts>()
+            .add_startup_system(setup_midi)
+            .add_startup_system(setup_device_hud)
+            .add_system(discover_devices)
+            .add_system(reconnect_last_device.after(discover_devices))
+            .add_system(release_stuck_keys)
+            .add_system(release_keys_on_focus_loss)
+            .add_system(apply_background_input)
+            .add_system(load_note_remap)
+            .add_system(select_device)
+            .add_system(select_device_ui)
+            .add_system(input_state_ui)
+            .add_system(octave_ui)
+            .add_system(device_hud)
+            .add_system(midi_error_toast_ui);
+    }
+}
+
+// Initializes the MIDI input instance and adds as a resource
+fn setup_midi(mut commands: Commands, mut error_events: EventWriter<MidiErrorEvent>) {
+    match create_midi_input() {
+        Ok(midi_in) => commands.insert_resource(MidiSetupState {
+            input: midi_in,
+            available_ports: Vec::new(),
+        }),
+        Err(error) => {
+            println!("Error {}", error);
+            error_events.send(MidiErrorEvent(error));
+        }
+    }
+}
+
+// Constantly updates available devices
+fn discover_devices(midi_state: Option<ResMut<MidiSetupState>>) {
+    // No MIDI input available? Nothing to discover.
+    let Some(mut midi_state) = midi_state else {
+        return;
+    };
+
+    // Get all available ports
+    // We keep polling while connected so devices can be switched mid-session
+    midi_state.available_ports = midi_state.input.ports();
+}
+
+// Connects to the last selected device once it shows up, so it doesn't have to be picked every launch
+// Only happens once, after that the user is in charge of switching devices
+fn reconnect_last_device(
+    midi_state: Option<Res<MidiSetupState>>,
+    input_state: Res<MidiInputState>,
+    settings: Res<Settings>,
+    mut device_event: EventWriter<SelectDeviceEvent>,
+    mut reconnected: Local<bool>,
+) {
+    if *reconnected || input_state.connected {
+        *reconnected = true;
+        return;
+    }
+
+    let (Some(midi_state), Some(device_name)) = (midi_state, settings.device.as_ref()) else {
+        return;
+    };
+
+    // Keep looking until it's plugged in
+    let port = midi_state.available_ports.iter().position(|port| {
+        midi_state
+            .input
+            .port_name(port)
+            .is_ok_and(|name| name == *device_name)
+    });
+    if let Some(index) = port {
+        println!("Reconnecting to {}", device_name);
+        device_event.send(SelectDeviceEvent(index));
+        *reconnected = true;
+    }
+}
+
+// Checks MIDI message channel for new key inputs each frame
+fn sync_keys(
+    input_reader: Res<MidiInputReader>,
+    mut input_state: ResMut<MidiInputState>,
+    mut key_events: EventWriter<MidiInputKey>,
+    note_remap: Res<NoteRemap>,
+    settings: Option<Res<Settings>>,
+    time: Res<Time>,
+) {
+    // Drain everything that arrived since last frame
+    let messages: Vec<MidiResponse> = input_reader.receiver.try_iter().collect();
+    if messages.is_empty() {
+        return;
+    }
+
+    // Aftertouch can flood the channel, so we only keep the latest value per key
+    // (or for the whole channel). Presses and releases are never dropped.
+    let coalesce_aftertouch = settings.is_none_or(|settings| settings.coalesce_aftertouch);
+    let aftertouch_target = |message: &MidiMessage| match message {
+        MidiMessage::Key(key) if matches!(key.event, MidiEvents::Holding) => Some(Some(key.id)),
+        MidiMessage::ChannelPressure(_) => Some(None),
+        MidiMessage::Key(_) => None,
+    };
+    let mut latest_aftertouch = HashMap::new();
+    if coalesce_aftertouch {
+        for (index, MidiResponse(message, _)) in messages.iter().enumerate() {
+            if let Some(target) = aftertouch_target(message) {
+                latest_aftertouch.insert(target, index);
+            }
+        }
+    }
+
+    for (index, MidiResponse(message, source)) in messages.into_iter().enumerate() {
+        if coalesce_aftertouch
+            && aftertouch_target(&message)
+                .is_some_and(|target| latest_aftertouch.get(&target) != Some(&index))
+        {
+            continue;
+        }
+
+        let mut key = match message {
+            MidiMessage::Key(key) => key,
+            // Channel pressure applies to every held key (already remapped, so we skip that)
+            MidiMessage::ChannelPressure(pressure) => {
+                let now = time.elapsed_seconds();
+                let keys: Vec<MidiInputKey> = input_state
+                    .held_keys
+                    .iter_mut()
+                    .map(|(id, held_key)| {
+                        held_key.pressed_at = now;
+                        MidiInputKey {
+                            event: MidiEvents::Holding,
+                            id: *id,
+                            intensity: pressure,
+                        }
+                    })
+                    .collect();
+                if let Some(key) = keys.last() {
+                    input_state.latest_key = Some(*key);
+                }
+                key_events.send_batch(keys);
+                continue;
+            }
+        };
+
+        // This runs for every key, so keep it out of the default log output
+        debug!("Key detected: {:?} {}", key.event, key.id);
+
+        // Translate the note to the id the game expects
+        // The remap belongs to the connected device, so other sources are left alone
+        if source == MidiSource::Device {
+            if let Some(id) = note_remap.0.get(&key.id) {
+                key.id = *id;
+            }
+        }
+        let Some(id) = input_state.apply_octave(key.id) else {
+            continue;
+        };
+        key.id = id;
+
+        // Some drivers re-send "note on" while a key is held, treat those as holds
+        if matches!(key.event, MidiEvents::Pressed) && input_state.held_keys.contains_key(&key.id) {
+            key.event = MidiEvents::Holding;
+        }
+
+        // Track which keys are held so we can catch dropped releases
+        match key.event {
+            MidiEvents::Pressed => {
+                input_state.held_keys.insert(
+                    key.id,
+                    HeldKey {
+                        pressed_at: time.elapsed_seconds(),
+                        intensity: key.intensity,
+                        source,
+                    },
+                );
+            }
+            MidiEvents::Released => {
+                input_state.held_keys.remove(&key.id);
+            }
+            MidiEvents::Holding => {
+                // The key is still in use, so it isn't stuck
+                if let Some(held_key) = input_state.held_keys.get_mut(&key.id) {
+                    held_key.pressed_at = time.elapsed_seconds();
+                }
+            }
+        }
+
+        // Let other systems react to every key, not just the latest one
+        key_events.send(key);
+        input_state.latest_key = Some(key);
+    }
+}
+
+// Releases keys that have been held suspiciously long
+// This usually means the device dropped a "note off" message (e.g. flaky USB connection)
+fn release_stuck_keys(
+    mut input_state: ResMut<MidiInputState>,
+    mut key_events: EventWriter<MidiInputKey>,
+    settings: Res<Settings>,
+    time: Res<Time>,
+) {
+    let now = time.elapsed_seconds();
+    let stuck_keys: Vec<u8> = input_state
+        .held_keys
+        .iter()
+        .filter(|(_, held_key)| now - held_key.pressed_at > settings.max_note_hold)
+        .map(|(id, _)| *id)
+        .collect();
+
+    for id in stuck_keys {
+        println!(
+            "Warning: key {} held longer than {}s, releasing it. Likely a dropped MIDI message.",
+            id, settings.max_note_hold
+        );
+
+        input_state.held_keys.remove(&id);
+        input_state.stuck_keys_released += 1;
+
+        // Send the release like the device would have
+        // (ids in `held_keys` are already remapped, so we skip the message channel)
+        let release = MidiInputKey {
+            event: MidiEvents::Released,
+            id,
+            intensity: 0,
+        };
+        key_events.send(release);
+        input_state.latest_key = Some(release);
+    }
+}
+
+// Creates a MIDI input instance that listens to every message type
+fn create_midi_input() -> Result<MidiInput, MidiError> {
+    let mut input = MidiInput::new("midir reading input")
+        .map_err(|error| MidiError::InitFailed(error.to_string()))?;
+    input.ignore(Ignore::None);
+    Ok(input)
+}
+
+// Connects to the device at `device_id` and forwards its keys into the message channel
+fn connect_device(
+    device_id: usize,
+    sender: Sender<MidiResponse>,
+) -> Result<(MidiInputConnection<()>, String), MidiError> {
+    // Create a new MIDI input instance
+    // We do this here instead of using MidiSetupState because `connect()` consumes instance
+    let input = create_midi_input()?;
+    let ports = input.ports();
+
+    // Grab the port based on the port index from the event
+    let device_port = ports
+        .get(device_id)
+        .ok_or(MidiError::InvalidPortIndex(device_id))?;
+    let device_name = input
+        .port_name(device_port)
+        .map_err(|_| MidiError::PortNotFound)?;
+
+    println!("Connecting...");
+    // Connect to device!
+    let connection = input
+        .connect(
+            device_port,
+            "midir-read-input",
+            move |stamp, message, _| {
+                // This runs for every raw message, so keep it out of the default log output
+                debug!("{}: {:?} (len = {})", stamp, message, message.len());
+                // stamp = incrementing time
+                let Some(message) = MidiMessage::from_bytes(message) else {
+                    return;
+                };
+
+                // Send the message via message channel to reach outside this callback
+                let _ = sender.send(MidiResponse(message, MidiSource::Device));
+            },
+            (),
+        )
+        .map_err(|error| MidiError::ConnectFailed(error.to_string()))?;
+
+    Ok((connection, device_name))
+}
+
+// Loads the connected device's note remapping when the device changes
+fn load_note_remap(
+    device_info: Option<Res<ConnectedDeviceInfo>>,
+    mut note_remap: ResMut<NoteRemap>,
+) {
+    let Some(device_info) = device_info.filter(|device_info| device_info.is_changed()) else {
+        return;
+    };
+
+    note_remap.0 = settings::load_note_remap(&device_info.name);
+}
+
+// Checks for device connection events, connects to device, and stores connection as resource
+// The events are queried through a `SystemState` that persists between runs,
+// so each event reader only sees events it hasn't read yet
+fn select_device(
+    world: &mut World,
+    event_system_state: &mut SystemState<(
+        EventReader<SelectDeviceEvent>,
+        EventReader<DisconnectDeviceEvent>,
+    )>,
+) {
+    let (mut device_events, mut disconnect_events) = event_system_state.get(world);
+    let disconnect = disconnect_events.iter().count() > 0;
+    let device_ids: Vec<usize> = device_events
+        .iter()
+        .map(|SelectDeviceEvent(device_id)| *device_id)
+        .collect();
+
+    // Disconnect requests are handled before any new selection
+    if disconnect {
+        disconnect_device(world);
+    }
+
+    // Store the connection in an optional variable
+    let mut connection_result = None;
+    let mut errors = Vec::new();
+
+    // Loop over all device events if there's any
+    let sender = world.resource::<MidiInputReader>().sender();
+    for device_id in device_ids {
+        match connect_device(device_id, sender.clone()) {
+            // Store the connection for later
+            Ok(connection) => connection_result = Some(connection),
+            Err(error) => errors.push(error),
+        }
+    }
+
+    for error in errors {
+        println!("Error {}", error);
+        world.send_event(MidiErrorEvent(error));
+    }
+
+    // Add the connection as a "non-send" resource.
+    // Lets it persist past this system.
+    // And connection can't be used across threads so this enforces main thread only
+    if let Some((connection, device_name)) = connection_result {
+        // Switching devices: only drop the old one now that the new one is connected
+        disconnect_device(world);
+        world.insert_non_send_resource(connection);
+        world.insert_resource(ConnectedDeviceInfo { name: device_name });
+        world.resource_mut::<MidiInputState>().connected = true;
+    }
+}
+
+// Drops the current connection and releases any keys still held on it
+fn disconnect_device(world: &mut World) {
+    if world
+        .remove_non_send_resource::<MidiInputConnection<()>>()
+        .is_none()
+    {
+        return;
+    }
+
+    println!("Disconnected");
+    world.remove_resource::<ConnectedDeviceInfo>();
+    // The remap was for that device, keep it from applying to the next one
+    world.resource_mut::<NoteRemap>().0.clear();
+
+    let mut input_state = world.resource_mut::<MidiInputState>();
+    input_state.connected = false;
+
+    // The device can't send releases anymore, so we do it for them
+    // Keys held over other inputs (e.g. OSC) are left alone
+    let releases = input_state.release_from(MidiSource::Device);
+    world.send_event_batch(releases);
+}
+
+// Switches between running input-only in the background and idling when unfocused
+fn apply_background_input(settings: Res<Settings>, mut winit_settings: ResMut<WinitSettings>) {
+    if !settings.is_changed() {
+        return;
+    }
+
+    winit_settings.unfocused_mode = if settings.background_input {
+        // Keep draining MIDI every frame while unfocused or minimized
+        UpdateMode::Continuous
+    } else {
+        UpdateMode::ReactiveLowPower {
+            max_wait: Duration::from_secs(1),
+        }
+    };
+}
+
+// Sends "all notes off" when the window loses focus, unless we're running in the background
+fn release_keys_on_focus_loss(
+    mut focus_events: EventReader<WindowFocused>,
+    settings: Res<Settings>,
+    mut input_state: ResMut<MidiInputState>,
+    mut key_events: EventWriter<MidiInputKey>,
+) {
+    let lost_focus = focus_events.iter().any(|event| !event.focused);
+    if !lost_focus || settings.background_input || input_state.held_keys.is_empty() {
+        return;
+    }
+
+    let releases = input_state.release_all();
+    if let Some(release) = releases.last() {
+        input_state.latest_key = Some(*release);
+    }
+    key_events.send_batch(releases);
+}
+
+// The UI for selecting a device
+#[allow(clippy::too_many_arguments)]
+fn select_device_ui(
+    mut contexts: EguiContexts,
+    midi_state: Option<Res<MidiSetupState>>,
+    input_state: Res<MidiInputState>,
+    device_info: Option<Res<ConnectedDeviceInfo>>,
+    keyboard: Res<Input<KeyCode>>,
+    mut devices_window: ResMut<DevicesWindow>,
+    mut device_event: EventWriter<SelectDeviceEvent>,
+    mut disconnect_event: EventWriter<DisconnectDeviceEvent>,
+    mut settings: ResMut<Settings>,
+) {
+    if keyboard.just_pressed(DEVICES_WINDOW_KEY) {
+        devices_window.open = !devices_window.open;
+    }
+
+    let Some(midi_state) = midi_state else {
+        return;
+    };
+
+    let context = contexts.ctx_mut();
+    egui::Window::new("Devices")
+        .open(&mut devices_window.open)
+        // Keep the window on screen when the app window is resized
+        .constrain(true)
+        .show(context, |ui| {
+            let connected_name = device_info
+                .as_ref()
+                .filter(|_| input_state.connected)
+                .map(|device_info| device_info.name.as_str());
+
+            let ports = midi_state.available_ports.iter().enumerate();
+            for (index, port) in ports {
+                // Skip ports that disappeared since the last discovery
+                let Ok(device_name) = midi_state.input.port_name(port) else {
+                    continue;
+                };
+                let is_connected = connected_name == Some(device_name.as_str());
+                if ui.selectable_label(is_connected, &device_name).clicked() && !is_connected {
+                    println!("Selecting device {}", &device_name);
+                    // Switching devices mid-session replaces the old one once the new one connects
+                    device_event.send(SelectDeviceEvent(index));
+                    settings.device = Some(device_name);
+                }
+            }
+
+            if connected_name.is_some() && ui.button("Disconnect").clicked() {
+                disconnect_event.send(DisconnectDeviceEvent);
+            }
+
+            ui.small(format!(
+                "Press {:?} to toggle this window",
+                DEVICES_WINDOW_KEY
+            ));
+        });
+}
+
+// The UI for selecting a device
+fn input_state_ui(
+    mut contexts: EguiContexts,
+    input_state: Res<MidiInputState>,
+    mut settings: ResMut<Settings>,
+) {
+    let context = contexts.ctx_mut();
+    egui::Window::new("Input state")
+        .constrain(true)
+        .show(context, |ui| {
+            if let Some(latest_key) = &input_state.latest_key {
+                ui.heading("Latest key");
+
+                let name = latest_key.id.to_string();
+                ui.horizontal(|ui| {
+                    ui.strong("Key");
+                    ui.label(name);
+                });
+
+                let intensity = latest_key.intensity.to_string();
+                ui.horizontal(|ui| {
+                    ui.strong("Intensity");
+                    ui.label(intensity);
+                });
+            }
+
+            ui.heading("Debug");
+
+            let stuck_keys_released = input_state.stuck_keys_released.to_string();
+            ui.horizontal(|ui| {
+                ui.strong("Stuck keys released");
+                ui.label(stuck_keys_released);
+            });
+
+            // Only write back on change so settings aren't saved every frame
+            let mut show_pitch_ring = settings.show_pitch_ring;
+            ui.checkbox(&mut show_pitch_ring, "Show pitch classes");
+            if show_pitch_ring != settings.show_pitch_ring {
+                settings.show_pitch_ring = show_pitch_ring;
+            }
+
+            let mut background_input = settings.background_input;
+            ui.checkbox(
+                &mut background_input,
+                "Keep reading input in the background",
+            );
+            if background_input != settings.background_input {
+                settings.background_input = background_input;
+            }
+        });
+}
+
+// The UI for shifting incoming keys by octaves
+fn octave_ui(
+    mut contexts: EguiContexts,
+    mut input_state: ResMut<MidiInputState>,
+    mut key_events: EventWriter<MidiInputKey>,
+) {
+    let context = contexts.ctx_mut();
+    egui::Window::new("Octave")
+        .constrain(true)
+        .show(context, |ui| {
+            let mut octave = input_state.octave;
+            ui.horizontal(|ui| {
+                if ui.button("-").clicked() && octave > -MAX_OCTAVE_OFFSET {
+                    octave -= 1;
+                }
+                ui.strong(format!("{:+}", octave));
+                if ui.button("+").clicked() && octave < MAX_OCTAVE_OFFSET {
+                    octave += 1;
+                }
+            });
+
+            // Show where the device's middle C ends up
+            if let Some(base_note) = input_state.apply_octave(MIDDLE_C) {
+                ui.horizontal(|ui| {
+                    ui.strong("Middle C plays");
+                    ui.label(format!("{} ({})", note_name(base_note), base_note));
+                });
+            }
+
+            // Only touch state when changed so we don't trigger change detection every frame
+            // Held keys now sound at a different pitch, so listeners hear them move
+            if octave != input_state.octave {
+                key_events.send_batch(input_state.set_octave(octave));
+            }
+        });
+}
+
+// Spawns the persistent HUD label for the connected device
+fn setup_device_hud(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.spawn(Camera2dBundle::default());
+
+    commands.spawn((
+        Text2dBundle {
+            text: Text::from_section(
+                "No Device",
+                TextStyle {
+                    font: asset_server.load(DEVICE_LABEL_FONT),
+                    font_size: 20.0,
+                    color: Color::RED,
+                },
+            ),
+            text_anchor: bevy::sprite::Anchor::TopRight,
+            ..default()
+        },
+        ConnectedDeviceLabel,
+    ));
+}
+
+// Keeps the device label pinned to the top-right corner and in sync with the connection
+fn device_hud(
+    windows: Query<&Window>,
+    input_state: Res<MidiInputState>,
+    device_info: Option<Res<ConnectedDeviceInfo>>,
+    mut labels: Query<(&mut Text, &mut Transform), With<ConnectedDeviceLabel>>,
+) {
+    let Ok(window) = windows.get_single() else {
+        return;
+    };
+
+    let device_changed = device_info
+        .as_ref()
+        .is_some_and(|device_info| device_info.is_changed());
+
+    for (mut text, mut transform) in labels.iter_mut() {
+        // The 2D camera is centered, so the corner is half the window away
+        transform.translation.x = window.width() / 2.0 - DEVICE_LABEL_MARGIN;
+        transform.translation.y = window.height() / 2.0 - DEVICE_LABEL_MARGIN;
+
+        if !input_state.is_changed() && !device_changed {
+            continue;
+        }
+
+        let section = &mut text.sections[0];
+        match (&device_info, input_state.connected) {
+            (Some(device_info), true) => {
+                section.value = device_info.name.clone();
+                section.style.color = Color::WHITE;
+            }
+            _ => {
+                section.value = "No Device".to_string();
+                section.style.color = Color::RED;
+            }
+        }
+    }
+}
+
+// Shows recent MIDI errors as toasts in the bottom-right corner
+fn midi_error_toast_ui(
+    mut contexts: EguiContexts,
+    mut error_events: EventReader<MidiErrorEvent>,
+    mut toasts: ResMut<MidiErrorToasts>,
+    time: Res<Time>,
+) {
+    for MidiErrorEvent(error) in error_events.iter() {
+        toasts.0.push((
+            error.clone(),
+            Timer::from_seconds(ERROR_TOAST_DURATION, TimerMode::Once),
+        ));
+    }
+
+    // Remove any toasts that have been shown long enough
+    toasts
+        .0
+        .retain_mut(|(_, timer)| !timer.tick(time.delta()).finished());
+    if toasts.0.is_empty() {
+        return;
+    }
+
+    let context = contexts.ctx_mut();
+    egui::Area::new("MIDI errors")
+        .anchor(egui::Align2::RIGHT_BOTTOM, egui::vec2(-16.0, -16.0))
+        .show(context, |ui| {
+            for (error, _) in toasts.0.iter() {
+                egui::Frame::popup(ui.style()).show(ui, |ui| {
+                    ui.colored_label(egui::Color32::RED, error.to_string());
+                });
+            }
+        });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{MidiEvents, MidiInputKey, MidiMessage};
+
+    #[test]
+    fn parses_every_channel_and_ignores_other_messages() {
+        let key = MidiInputKey::from_message(&[0x93, 60, 80]).expect("Note on wasn't parsed");
+        assert!(matches!(key.event, MidiEvents::Pressed));
+        assert_eq!(key.id, 60);
+
+        let key = MidiInputKey::from_message(&[0x8F, 60, 0]).expect("Note off wasn't parsed");
+        assert!(matches!(key.event, MidiEvents::Released));
+
+        // Control change and pitch bend aren't keys
+        assert!(MidiInputKey::from_message(&[0xB0, 1, 64]).is_none());
+        assert!(MidiInputKey::from_message(&[0xE0, 0, 64]).is_none());
+    }
+
+    #[test]
+    fn parses_channel_pressure_on_every_channel() {
+        let message = MidiMessage::from_bytes(&[0xD5, 90]);
+        assert!(matches!(message, Some(MidiMessage::ChannelPressure(90))));
+        assert!(MidiMessage::from_bytes(&[0xD0]).is_none());
+
+        // Keys still come through as keys
+        let message = MidiMessage::from_bytes(&[0xA2, 60, 40]);
+        assert!(matches!(
+            message,
+            Some(MidiMessage::Key(MidiInputKey {
+                event: MidiEvents::Holding,
+                id: 60,
+                intensity: 40,
+            }))
+        ));
+    }
+}
@@ -0,0 +1,96 @@
+use std::net::{SocketAddr, ToSocketAddrs, UdpSocket};
+
+use bevy::prelude::*;
+
+use crate::{settings::Settings, MidiInputKey};
+
+// Publishes every MIDI key over UDP so external tools can visualize input
+pub struct UdpBroadcastPlugin;
+
+impl Plugin for UdpBroadcastPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<UdpBroadcastTarget>()
+            .add_startup_system(setup_udp_broadcast)
+            .add_system(resolve_broadcast_address)
+            .add_system(broadcast_keys.after(resolve_broadcast_address));
+    }
+}
+
+// The socket we send keys from
+pub struct UdpBroadcastSocket(UdpSocket);
+
+// Where keys get sent, None if the address setting couldn't be resolved
+#[derive(Resource, Default)]
+pub struct UdpBroadcastTarget(Option<SocketAddr>);
+
+// Binds a non-blocking socket so a slow or missing listener never stalls a frame
+fn setup_udp_broadcast(world: &mut World) {
+    let socket = match UdpSocket::bind("0.0.0.0:0") {
+        Ok(socket) => socket,
+        Err(error) => {
+            println!("Couldn't bind UDP broadcast socket: {}", error);
+            return;
+        }
+    };
+
+    if let Err(error) = socket.set_nonblocking(true) {
+        println!("Couldn't make UDP broadcast socket non-blocking: {}", error);
+        return;
+    }
+
+    world.insert_non_send_resource(UdpBroadcastSocket(socket));
+}
+
+// Resolves the broadcast address when the setting changes, so we don't look it up for every key
+fn resolve_broadcast_address(
+    settings: Res<Settings>,
+    mut target: ResMut<UdpBroadcastTarget>,
+    mut resolved_address: Local<String>,
+) {
+    let address = &settings.udp_broadcast_address;
+    if !settings.is_changed() || *resolved_address == *address {
+        return;
+    }
+    *resolved_address = address.clone();
+
+    target.0 = match address.to_socket_addrs() {
+        Ok(mut addresses) => addresses.next(),
+        Err(error) => {
+            println!(
+                "Couldn't resolve UDP broadcast address {}: {}",
+                address, error
+            );
+            None
+        }
+    };
+}
+
+// Sends each key as [status, note, velocity, timestamp (ms, little endian u32)]
+fn broadcast_keys(
+    socket: Option<NonSend<UdpBroadcastSocket>>,
+    settings: Res<Settings>,
+    target: Res<UdpBroadcastTarget>,
+    time: Res<Time>,
+    mut key_events: EventReader<MidiInputKey>,
+) {
+    let (Some(socket), Some(address)) = (socket.filter(|_| settings.udp_broadcast), target.0)
+    else {
+        // Clear out events so they don't get sent late when broadcasting is enabled
+        key_events.clear();
+        return;
+    };
+
+    let timestamp = time.elapsed().as_millis() as u32;
+    for key in key_events.iter() {
+        let mut packet = [0; 7];
+        packet[0] = key.event.status();
+        packet[1] = key.id;
+        packet[2] = key.intensity;
+        packet[3..].copy_from_slice(&timestamp.to_le_bytes());
+
+        // Dropping a packet is fine, the next key will carry on
+        if let Err(error) = socket.0.send_to(&packet, address) {
+            println!("Couldn't broadcast MIDI key: {}", error);
+        }
+    }
+}
@@ -0,0 +1,196 @@
+use std::net::UdpSocket;
+
+use bevy::prelude::*;
+use crossbeam_channel::Sender;
+use rosc::{OscMessage, OscPacket, OscType};
+
+use crate::{
+    settings::Settings, MidiEvents, MidiInputKey, MidiInputReader, MidiMessage, MidiResponse,
+    MidiSource,
+};
+
+// Where we listen for OSC messages (SuperCollider's default port)
+const OSC_ADDRESS: &str = "0.0.0.0:57120";
+// Largest OSC packet we read at once
+const OSC_BUFFER_SIZE: usize = 1536;
+
+// Receives `/note/on` and `/note/off` OSC messages as an alternative to a MIDI device
+pub struct OscInputPlugin;
+
+impl Plugin for OscInputPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(toggle_osc_input).add_system(read_osc_input);
+    }
+}
+
+// The socket we receive OSC messages on
+pub struct OscInputSocket(UdpSocket);
+
+// Opens or closes the OSC socket when the `osc_enabled` setting changes.
+// A failed bind isn't retried until OSC is turned off and on again,
+// but the setting itself is left alone so it still applies next launch.
+fn toggle_osc_input(world: &mut World, mut bind_failed: Local<bool>) {
+    let osc_enabled = world.resource::<Settings>().osc_enabled;
+    let has_socket = world.contains_non_send::<OscInputSocket>();
+
+    if !osc_enabled {
+        *bind_failed = false;
+        if has_socket {
+            world.remove_non_send_resource::<OscInputSocket>();
+        }
+        return;
+    }
+
+    if has_socket || *bind_failed {
+        return;
+    }
+
+    // Non-blocking so reading never stalls a frame
+    let socket = UdpSocket::bind(OSC_ADDRESS).and_then(|socket| {
+        socket.set_nonblocking(true)?;
+        Ok(socket)
+    });
+
+    match socket {
+        Ok(socket) => {
+            println!("Listening for OSC on {}", OSC_ADDRESS);
+            world.insert_non_send_resource(OscInputSocket(socket));
+        }
+        Err(error) => {
+            println!("Couldn't listen for OSC on {}: {}", OSC_ADDRESS, error);
+            // Don't retry every frame
+            *bind_failed = true;
+        }
+    }
+}
+
+// Reads every pending OSC packet and forwards notes into the MIDI message channel,
+// so they go through the same octave shift and held-key tracking as device input
+fn read_osc_input(socket: Option<NonSend<OscInputSocket>>, input_reader: Res<MidiInputReader>) {
+    let Some(socket) = socket else {
+        return;
+    };
+
+    let mut buffer = [0; OSC_BUFFER_SIZE];
+    while let Ok(size) = socket.0.recv(&mut buffer) {
+        match rosc::decoder::decode_udp(&buffer[..size]) {
+            Ok((_, packet)) => send_packet(packet, &input_reader.sender),
+            Err(error) => println!("Couldn't decode OSC packet: {:?}", error),
+        }
+    }
+}
+
+fn send_packet(packet: OscPacket, sender: &Sender<MidiResponse>) {
+    match packet {
+        OscPacket::Message(message) => {
+            if let Some(key) = key_from_message(&message) {
+                let _ = sender.send(MidiResponse(MidiMessage::Key(key), MidiSource::Osc));
+            }
+        }
+        OscPacket::Bundle(bundle) => {
+            for packet in bundle.content {
+                send_packet(packet, sender);
+            }
+        }
+    }
+}
+
+// Converts `/note/on [note, velocity]` and `/note/off [note, velocity]` into a key.
+// Values outside the MIDI range are rejected rather than clamped onto a different note.
+fn key_from_message(message: &OscMessage) -> Option<MidiInputKey> {
+    let (Some(OscType::Int(note)), Some(OscType::Int(velocity))) =
+        (message.args.first(), message.args.get(1))
+    else {
+        return None;
+    };
+
+    let midi_range = 0..=127;
+    if !midi_range.contains(note) || !midi_range.contains(velocity) {
+        return None;
+    }
+
+    let event = match (message.addr.as_str(), velocity) {
+        // Same as a device, "note on" with no velocity is a release
+        ("/note/on", 0) => MidiEvents::Released,
+        ("/note/on", _) => MidiEvents::Pressed,
+        ("/note/off", _) => MidiEvents::Released,
+        _ => return None,
+    };
+
+    Some(MidiInputKey {
+        event,
+        id: *note as u8,
+        intensity: *velocity as u8,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use rosc::{OscMessage, OscType};
+
+    use super::key_from_message;
+    use crate::MidiEvents;
+
+    fn message(addr: &str, args: Vec<OscType>) -> OscMessage {
+        OscMessage {
+            addr: addr.to_string(),
+            args,
+        }
+    }
+
+    #[test]
+    fn note_on_is_pressed() {
+        let key = key_from_message(&message(
+            "/note/on",
+            vec![OscType::Int(60), OscType::Int(80)],
+        ))
+        .expect("Note on wasn't parsed");
+        assert!(matches!(key.event, MidiEvents::Pressed));
+        assert_eq!(key.id, 60);
+        assert_eq!(key.intensity, 80);
+    }
+
+    #[test]
+    fn note_on_without_velocity_is_released() {
+        let key = key_from_message(&message(
+            "/note/on",
+            vec![OscType::Int(60), OscType::Int(0)],
+        ))
+        .expect("Note on wasn't parsed");
+        assert!(matches!(key.event, MidiEvents::Released));
+    }
+
+    #[test]
+    fn note_off_is_released() {
+        let key = key_from_message(&message(
+            "/note/off",
+            vec![OscType::Int(60), OscType::Int(0)],
+        ))
+        .expect("Note off wasn't parsed");
+        assert!(matches!(key.event, MidiEvents::Released));
+        assert_eq!(key.id, 60);
+    }
+
+    #[test]
+    fn ignores_other_addresses() {
+        let args = vec![OscType::Int(60), OscType::Int(80)];
+        assert!(key_from_message(&message("/cc", args)).is_none());
+    }
+
+    #[test]
+    fn ignores_non_int_args() {
+        let args = vec![OscType::Float(60.0), OscType::Int(80)];
+        assert!(key_from_message(&message("/note/on", args)).is_none());
+        let args = vec![OscType::String("C4".to_string()), OscType::Int(80)];
+        assert!(key_from_message(&message("/note/on", args)).is_none());
+        assert!(key_from_message(&message("/note/on", vec![OscType::Int(60)])).is_none());
+    }
+
+    #[test]
+    fn rejects_values_outside_the_midi_range() {
+        for (note, velocity) in [(128, 80), (-1, 80), (60, 128), (60, -1)] {
+            let args = vec![OscType::Int(note), OscType::Int(velocity)];
+            assert!(key_from_message(&message("/note/on", args)).is_none());
+        }
+    }
+}
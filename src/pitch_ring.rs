@@ -0,0 +1,123 @@
+use std::f32::consts::TAU;
+
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts};
+
+use crate::{settings::Settings, MidiInputState, NOTE_NAMES};
+
+// How long a pitch class takes to fade out after release (in seconds)
+const PITCH_RING_FADE: f32 = 0.5;
+// Size of the ring in the UI (in points)
+const PITCH_RING_RADIUS: f32 = 80.0;
+const PITCH_RING_THICKNESS: f32 = 30.0;
+// Quads used to draw the curve of each segment
+const PITCH_RING_SEGMENT_STEPS: usize = 8;
+
+// A 12-segment ring that lights up the pitch classes currently held
+pub struct PitchRingPlugin;
+
+impl Plugin for PitchRingPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PitchRingState>()
+            .add_system(update_pitch_ring)
+            .add_system(pitch_ring_ui.after(update_pitch_ring));
+    }
+}
+
+// How lit up each pitch class is, from 0 (off) to 1 (held)
+#[derive(Resource, Default)]
+pub struct PitchRingState {
+    levels: [f32; 12],
+}
+
+// Lights up held pitch classes and fades out released ones
+fn update_pitch_ring(
+    input_state: Res<MidiInputState>,
+    mut ring_state: ResMut<PitchRingState>,
+    time: Res<Time>,
+) {
+    let mut held = [false; 12];
+    for id in input_state.held_keys.keys() {
+        held[*id as usize % 12] = true;
+    }
+
+    let fade = time.delta_seconds() / PITCH_RING_FADE;
+    for (level, held) in ring_state.levels.iter_mut().zip(held) {
+        *level = if held { 1.0 } else { (*level - fade).max(0.0) };
+    }
+}
+
+// Draws the ring with C at the top, going clockwise
+fn pitch_ring_ui(
+    mut contexts: EguiContexts,
+    ring_state: Res<PitchRingState>,
+    settings: Res<Settings>,
+) {
+    if !settings.show_pitch_ring {
+        return;
+    }
+
+    let context = contexts.ctx_mut();
+    egui::Window::new("Pitch classes")
+        .constrain(true)
+        .show(context, |ui| {
+            let size = egui::Vec2::splat(PITCH_RING_RADIUS * 2.0);
+            let (response, painter) = ui.allocate_painter(size, egui::Sense::hover());
+            let center = response.rect.center();
+            let inner_radius = PITCH_RING_RADIUS - PITCH_RING_THICKNESS;
+            let segment_angle = TAU / 12.0;
+
+            for (index, level) in ring_state.levels.iter().enumerate() {
+                // Start at the top instead of the right
+                let start = index as f32 * segment_angle - TAU / 4.0;
+                let point = |angle: f32, radius: f32| {
+                    center + egui::vec2(angle.cos(), angle.sin()) * radius
+                };
+
+                let off = egui::Color32::from_gray(40);
+                let on = egui::Color32::from_rgb(80, 160, 255);
+                let fill = egui::Color32::from_rgb(
+                    lerp(off.r(), on.r(), *level),
+                    lerp(off.g(), on.g(), *level),
+                    lerp(off.b(), on.b(), *level),
+                );
+
+                // The segment itself isn't convex, so we draw it as a strip of small quads
+                let step_angle = segment_angle / PITCH_RING_SEGMENT_STEPS as f32;
+                for step in 0..PITCH_RING_SEGMENT_STEPS {
+                    let from = start + step_angle * step as f32;
+                    let to = from + step_angle;
+                    painter.add(egui::Shape::convex_polygon(
+                        vec![
+                            point(from, PITCH_RING_RADIUS),
+                            point(to, PITCH_RING_RADIUS),
+                            point(to, inner_radius),
+                            point(from, inner_radius),
+                        ],
+                        fill,
+                        egui::Stroke::NONE,
+                    ));
+                }
+
+                // Separate segments from each other
+                painter.line_segment(
+                    [point(start, inner_radius), point(start, PITCH_RING_RADIUS)],
+                    egui::Stroke::new(1.0, egui::Color32::BLACK),
+                );
+
+                // Label the middle of the segment
+                let label_angle = start + segment_angle / 2.0;
+                painter.text(
+                    point(label_angle, inner_radius + PITCH_RING_THICKNESS / 2.0),
+                    egui::Align2::CENTER_CENTER,
+                    NOTE_NAMES[index],
+                    egui::FontId::proportional(12.0),
+                    egui::Color32::WHITE,
+                );
+            }
+        });
+}
+
+fn lerp(from: u8, to: u8, amount: f32) -> u8 {
+    (from as f32 + (to as f32 - from as f32) * amount) as u8
+}
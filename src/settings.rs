@@ -0,0 +1,138 @@
+use std::{collections::HashMap, fs, path::PathBuf};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+// Folder and file name inside the platform's config directory
+const SETTINGS_DIR: &str = "bevy-midi";
+const SETTINGS_FILE: &str = "settings.json";
+// Folder inside `SETTINGS_DIR` with per-device config files
+const DEVICES_DIR: &str = "devices";
+
+// User settings persisted between sessions
+#[derive(Resource, Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct Settings {
+    // Name of the last MIDI device the user selected
+    pub device: Option<String>,
+    // Longest a key can be held (in seconds) before we assume the release got lost
+    pub max_note_hold: f32,
+    // Framerate cap, 0 matches the monitor's refresh rate
+    pub max_fps: u32,
+    // Publish MIDI keys over UDP for external visualizers
+    pub udp_broadcast: bool,
+    // Where to send broadcast MIDI keys
+    pub udp_broadcast_address: String,
+    // Show the pitch class ring visualizer
+    pub show_pitch_ring: bool,
+    // Accept notes over OSC as well as MIDI
+    pub osc_enabled: bool,
+    // Only keep the latest aftertouch value per key each frame
+    pub coalesce_aftertouch: bool,
+    // Keep processing MIDI while unfocused or minimized (e.g. as a visualizer overlay).
+    // Turning it off idles when unfocused and releases held keys on focus loss.
+    pub background_input: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            device: None,
+            max_note_hold: 30.0,
+            max_fps: 60,
+            udp_broadcast: false,
+            udp_broadcast_address: "127.0.0.1:21928".to_string(),
+            show_pitch_ring: true,
+            osc_enabled: false,
+            coalesce_aftertouch: true,
+            background_input: true,
+        }
+    }
+}
+
+impl Settings {
+    // Path to the settings file, e.g. `~/.config/bevy-midi/settings.json` on Linux
+    fn path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join(SETTINGS_DIR).join(SETTINGS_FILE))
+    }
+
+    // Loads settings from disk, falling back to defaults if the file is missing or corrupt
+    pub fn load() -> Settings {
+        let Some(path) = Settings::path() else {
+            return Settings::default();
+        };
+
+        match fs::read_to_string(&path) {
+            Ok(contents) => serde_json::from_str(&contents).unwrap_or_else(|error| {
+                println!("Couldn't parse settings, using defaults: {}", error);
+                Settings::default()
+            }),
+            Err(_) => Settings::default(),
+        }
+    }
+
+    // Writes settings to disk, creating the config folder if needed
+    pub fn save(&self) -> std::io::Result<()> {
+        let Some(path) = Settings::path() else {
+            return Ok(());
+        };
+
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+
+        let contents = serde_json::to_string_pretty(self)?;
+        fs::write(path, contents)
+    }
+}
+
+// Loads the note remapping for a device from `devices/<device name>.json`,
+// a JSON object of incoming note id to game note id (e.g. `{ "36": 60 }`).
+// Missing or corrupt files mean no remapping.
+pub fn load_note_remap(device_name: &str) -> HashMap<u8, u8> {
+    // Device names can contain characters that aren't valid in file names
+    let file_name: String = device_name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    let Some(path) = dirs::config_dir().map(|dir| {
+        dir.join(SETTINGS_DIR)
+            .join(DEVICES_DIR)
+            .join(format!("{}.json", file_name))
+    }) else {
+        return HashMap::new();
+    };
+
+    match fs::read_to_string(&path) {
+        Ok(contents) => serde_json::from_str(&contents).unwrap_or_else(|error| {
+            println!(
+                "Couldn't parse note remapping for {}: {}",
+                device_name, error
+            );
+            HashMap::new()
+        }),
+        Err(_) => HashMap::new(),
+    }
+}
+
+// Loads the settings file into a resource and saves it whenever it changes
+pub struct SettingsPlugin;
+
+impl Plugin for SettingsPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Settings::load())
+            .add_system(save_settings);
+    }
+}
+
+// Persists settings to disk on change
+fn save_settings(settings: Res<Settings>) {
+    // Skip the first frame since we just loaded them
+    if !settings.is_changed() || settings.is_added() {
+        return;
+    }
+
+    if let Err(error) = settings.save() {
+        println!("Couldn't save settings: {}", error);
+    }
+}
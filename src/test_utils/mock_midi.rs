@@ -0,0 +1,294 @@
+use crossbeam_channel::Sender;
+
+use crate::{MidiMessage, MidiResponse, MidiSource};
+
+// Stands in for a MIDI device connection by pushing raw messages
+// through the same parsing and channel the `connect()` callback uses
+pub struct MockMidiSender {
+    sender: Sender<MidiResponse>,
+}
+
+impl MockMidiSender {
+    pub fn new(sender: Sender<MidiResponse>) -> Self {
+        Self { sender }
+    }
+
+    pub fn send_note_on(&self, note: u8, velocity: u8) {
+        self.send_message(&[144, note, velocity]);
+    }
+
+    pub fn send_note_off(&self, note: u8) {
+        self.send_message(&[128, note, 0]);
+    }
+
+    pub fn send_cc(&self, controller: u8, value: u8) {
+        self.send_message(&[176, controller, value]);
+    }
+
+    pub fn send_aftertouch(&self, note: u8, pressure: u8) {
+        self.send_message(&[160, note, pressure]);
+    }
+
+    pub fn send_channel_pressure(&self, pressure: u8) {
+        self.send_message(&[208, pressure]);
+    }
+
+    fn send_message(&self, message: &[u8]) {
+        if let Some(message) = MidiMessage::from_bytes(message) {
+            self.sender
+                .send(MidiResponse(message, MidiSource::Device))
+                .expect("MIDI input channel closed");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use bevy::{ecs::event::Events, prelude::*};
+
+    use super::MockMidiSender;
+    use crate::{
+        MidiEvents, MidiInputKey, MidiInputPlugin, MidiInputReader, MidiInputState, MidiMessage,
+        MidiResponse, MidiSource, NoteRemap,
+    };
+
+    // An app with just the input pipeline and a mock device feeding it
+    fn app_with_mock() -> (App, MockMidiSender) {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins).add_plugin(MidiInputPlugin);
+
+        let mock = MockMidiSender::new(app.world.resource::<MidiInputReader>().sender());
+        (app, mock)
+    }
+
+    // Sends a key the way `OscInputPlugin` does, bypassing the mock device
+    fn send_osc_note_on(app: &App, note: u8, velocity: u8) {
+        let key = MidiInputKey {
+            event: MidiEvents::Pressed,
+            id: note,
+            intensity: velocity,
+        };
+        app.world
+            .resource::<MidiInputReader>()
+            .sender()
+            .send(MidiResponse(MidiMessage::Key(key), MidiSource::Osc))
+            .expect("MIDI input channel closed");
+    }
+
+    fn latest_event(app: &App) -> MidiEvents {
+        let input_state = app.world.resource::<MidiInputState>();
+        input_state.latest_key.expect("No key was synced").event
+    }
+
+    // Keys sent as events during the last update
+    fn synced_keys(app: &App) -> Vec<MidiInputKey> {
+        let events = app.world.resource::<Events<MidiInputKey>>();
+        events.iter_current_update_events().copied().collect()
+    }
+
+    #[test]
+    fn note_on_reaches_input_state() {
+        let (mut app, mock) = app_with_mock();
+        mock.send_note_on(60, 80);
+
+        app.update();
+        app.update();
+
+        let input_state = app.world.resource::<MidiInputState>();
+        let latest_key = input_state.latest_key.as_ref().expect("No key was synced");
+        assert!(matches!(latest_key.event, MidiEvents::Pressed));
+        assert_eq!(latest_key.id, 60);
+        assert_eq!(latest_key.intensity, 80);
+    }
+
+    #[test]
+    fn note_off_releases_held_key() {
+        let (mut app, mock) = app_with_mock();
+        mock.send_note_on(60, 80);
+        app.update();
+        assert!(app
+            .world
+            .resource::<MidiInputState>()
+            .held_keys
+            .contains_key(&60));
+
+        mock.send_note_off(60);
+        app.update();
+
+        let input_state = app.world.resource::<MidiInputState>();
+        assert!(!input_state.held_keys.contains_key(&60));
+        let latest_key = input_state.latest_key.as_ref().expect("No key was synced");
+        assert!(matches!(latest_key.event, MidiEvents::Released));
+        assert_eq!(latest_key.id, 60);
+    }
+
+    #[test]
+    fn control_change_is_not_a_key() {
+        let (mut app, mock) = app_with_mock();
+        mock.send_note_on(60, 80);
+        app.update();
+
+        mock.send_cc(1, 64);
+        app.update();
+
+        assert!(synced_keys(&app).is_empty());
+        let input_state = app.world.resource::<MidiInputState>();
+        let latest_key = input_state.latest_key.expect("No key was synced");
+        assert!(matches!(latest_key.event, MidiEvents::Pressed));
+        assert_eq!(latest_key.id, 60);
+        assert_eq!(input_state.held_keys.len(), 1);
+        assert!(input_state.held_keys.contains_key(&60));
+    }
+
+    #[test]
+    fn octave_change_moves_held_keys() {
+        let (mut app, mock) = app_with_mock();
+        mock.send_note_on(60, 80);
+        mock.send_note_on(120, 90);
+        app.update();
+
+        let mut input_state = app.world.resource_mut::<MidiInputState>();
+        let keys = input_state.set_octave(1);
+
+        // Every old id is released before the new ones are pressed
+        assert_eq!(keys.len(), 3);
+        assert!(keys[..2]
+            .iter()
+            .all(|key| matches!(key.event, MidiEvents::Released)));
+        assert!(keys[..2].iter().any(|key| key.id == 60));
+        assert!(keys[..2].iter().any(|key| key.id == 120));
+        // 120 shifts past the MIDI range, so only 60 comes back
+        assert!(matches!(keys[2].event, MidiEvents::Pressed));
+        assert_eq!(keys[2].id, 72);
+        assert_eq!(keys[2].intensity, 80);
+
+        assert_eq!(input_state.held_keys.len(), 1);
+        assert!(input_state.held_keys.contains_key(&72));
+    }
+
+    #[test]
+    fn repeated_note_on_is_holding() {
+        let (mut app, mock) = app_with_mock();
+        mock.send_note_on(60, 80);
+        app.update();
+        assert!(matches!(latest_event(&app), MidiEvents::Pressed));
+
+        mock.send_note_on(60, 80);
+        app.update();
+        assert!(matches!(latest_event(&app), MidiEvents::Holding));
+    }
+
+    #[test]
+    fn note_on_after_release_is_pressed() {
+        let (mut app, mock) = app_with_mock();
+        mock.send_note_on(60, 80);
+        app.update();
+        mock.send_note_off(60);
+        app.update();
+        assert!(matches!(latest_event(&app), MidiEvents::Released));
+
+        mock.send_note_on(60, 80);
+        app.update();
+        assert!(matches!(latest_event(&app), MidiEvents::Pressed));
+    }
+
+    #[test]
+    fn note_on_without_velocity_releases() {
+        let (mut app, mock) = app_with_mock();
+        mock.send_note_on(60, 80);
+        app.update();
+        mock.send_note_on(60, 0);
+        app.update();
+
+        assert!(matches!(latest_event(&app), MidiEvents::Released));
+        let input_state = app.world.resource::<MidiInputState>();
+        assert!(!input_state.held_keys.contains_key(&60));
+    }
+
+    #[test]
+    fn aftertouch_is_coalesced_per_frame() {
+        let (mut app, mock) = app_with_mock();
+        mock.send_note_on(60, 80);
+        for pressure in 1..=5 {
+            mock.send_aftertouch(60, pressure);
+        }
+        mock.send_note_on(62, 70);
+        mock.send_note_off(62);
+        app.update();
+
+        let keys = synced_keys(&app);
+        let holding: Vec<_> = keys
+            .iter()
+            .filter(|key| matches!(key.event, MidiEvents::Holding))
+            .collect();
+        assert_eq!(holding.len(), 1);
+        assert_eq!(holding[0].intensity, 5);
+
+        let pressed = keys
+            .iter()
+            .filter(|key| matches!(key.event, MidiEvents::Pressed))
+            .count();
+        let released = keys
+            .iter()
+            .filter(|key| matches!(key.event, MidiEvents::Released))
+            .count();
+        assert_eq!(pressed, 2);
+        assert_eq!(released, 1);
+    }
+
+    #[test]
+    fn channel_pressure_holds_every_key() {
+        let (mut app, mock) = app_with_mock();
+        mock.send_note_on(60, 80);
+        mock.send_note_on(64, 80);
+        app.update();
+
+        for pressure in 1..=5 {
+            mock.send_channel_pressure(pressure);
+        }
+        app.update();
+
+        let keys = synced_keys(&app);
+        assert_eq!(keys.len(), 2);
+        for key in keys {
+            assert!(matches!(key.event, MidiEvents::Holding));
+            assert!(key.id == 60 || key.id == 64);
+            assert_eq!(key.intensity, 5);
+        }
+    }
+
+    #[test]
+    fn note_remap_only_applies_to_the_device() {
+        let (mut app, mock) = app_with_mock();
+        app.insert_resource(NoteRemap(HashMap::from([(36, 60)])));
+
+        mock.send_note_on(36, 80);
+        // The same note over OSC, which isn't covered by the device's remap
+        send_osc_note_on(&app, 36, 80);
+        app.update();
+
+        let ids: Vec<u8> = synced_keys(&app).iter().map(|key| key.id).collect();
+        assert_eq!(ids, vec![60, 36]);
+        let input_state = app.world.resource::<MidiInputState>();
+        assert!(input_state.held_keys.contains_key(&60));
+        assert!(input_state.held_keys.contains_key(&36));
+    }
+
+    #[test]
+    fn device_release_leaves_other_sources_held() {
+        let (mut app, mock) = app_with_mock();
+        mock.send_note_on(60, 80);
+        send_osc_note_on(&app, 64, 80);
+        app.update();
+
+        let mut input_state = app.world.resource_mut::<MidiInputState>();
+        let keys = input_state.release_from(MidiSource::Device);
+        assert_eq!(keys.len(), 1);
+        assert!(matches!(keys[0].event, MidiEvents::Released));
+        assert_eq!(keys[0].id, 60);
+        assert_eq!(input_state.held_keys.len(), 1);
+        assert!(input_state.held_keys.contains_key(&64));
+    }
+}
@@ -0,0 +1,2 @@
+// Helpers for running the MIDI pipeline without hardware
+pub mod mock_midi;